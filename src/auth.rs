@@ -0,0 +1,391 @@
+use crate::config::{AuthBackend, Config};
+use crate::logs::{log_error, log_info, log_warn};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::time::Instant;
+
+/// Tracks authentication state for the current `elev` invocation, including
+/// when the user last successfully authenticated and whether that is still
+/// within the configured timeout. The timestamp is held only in this
+/// process's memory and is not persisted, so each new invocation starts
+/// unauthenticated.
+pub struct AuthState {
+    timeout: u64,
+    user: String,
+    groups: Vec<String>,
+    authenticated_at: Option<Instant>,
+}
+
+impl AuthState {
+    pub fn new(timeout: u64, user: String, groups: Vec<String>) -> Self {
+        AuthState { timeout, user, groups, authenticated_at: None }
+    }
+
+    /// Returns true if a prior authentication is still within the configured
+    /// timeout window, meaning the password prompt can be skipped.
+    pub fn check_timeout(&self) -> bool {
+        match self.authenticated_at {
+            Some(t) => t.elapsed().as_secs() < self.timeout,
+            None => false,
+        }
+    }
+
+    /// Record a fresh, successful authentication.
+    pub fn mark_authenticated(&mut self) {
+        self.authenticated_at = Some(Instant::now());
+    }
+
+    /// Invalidate any cached authentication (`-K`).
+    pub fn invalidate(&mut self) {
+        self.authenticated_at = None;
+    }
+
+    /// The invoking user this state was created for.
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// The invoking user's group memberships.
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+}
+
+/// Prompt `user` for their password and verify it using the backend selected
+/// in `config`, marking `auth_state` authenticated on success.
+pub fn verify_password(
+    user: &str,
+    auth_state: &mut AuthState,
+    config: &Config,
+    target_user: &str,
+    command: &str,
+) -> bool {
+    let prompt = format!("[elev] password for {}: ", user);
+
+    let ok = match &config.auth_backend {
+        AuthBackend::Internal => verify_internal(user, &prompt),
+        AuthBackend::Pam { service } => verify_pam(user, service, &prompt),
+    };
+
+    if ok {
+        log_info(&format!(
+            "authentication succeeded for '{}' (target '{}', command '{}')",
+            user, target_user, command
+        ));
+        auth_state.mark_authenticated();
+    } else {
+        log_warn(&format!(
+            "authentication failed for '{}' (target '{}', command '{}')",
+            user, target_user, command
+        ));
+    }
+
+    ok
+}
+
+/// elev's built-in password check against the local shadow database.
+fn verify_internal(user: &str, prompt: &str) -> bool {
+    let mut password = match rpassword::prompt_password(prompt) {
+        Ok(p) => p,
+        Err(e) => {
+            log_error(&format!("failed to read password: {}", e));
+            return false;
+        }
+    };
+
+    let result = check_shadow_password(user, &password);
+    zero_string(&mut password);
+    result
+}
+
+fn check_shadow_password(user: &str, password: &str) -> bool {
+    let cuser = match CString::new(user) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+
+    unsafe {
+        let spwd = libc::getspnam(cuser.as_ptr());
+        if spwd.is_null() {
+            return false;
+        }
+
+        let hash_ptr = (*spwd).sp_pwdp;
+        if hash_ptr.is_null() {
+            return false;
+        }
+
+        let chash = CStr::from_ptr(hash_ptr).to_owned();
+        let cpass = match CString::new(password) {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+
+        let result_ptr = crypt(cpass.as_ptr(), chash.as_ptr());
+        !result_ptr.is_null() && CStr::from_ptr(result_ptr) == chash.as_c_str()
+    }
+}
+
+// Not exposed by the `libc` crate; glibc provides it directly (merged from
+// libcrypt since 2.28), so no extra `-lcrypt` is needed on modern Linux.
+extern "C" {
+    fn crypt(key: *const c_char, salt: *const c_char) -> *mut c_char;
+}
+
+/// Authenticate `user` through PAM under the given service name, running the
+/// standard `pam_authenticate` + `pam_acct_mgmt` sequence so account expiry
+/// and lockout policy are honored the way sudo's would be.
+fn verify_pam(user: &str, service: &str, prompt: &str) -> bool {
+    let mut password = match rpassword::prompt_password(prompt) {
+        Ok(p) => p,
+        Err(e) => {
+            log_error(&format!("failed to read password: {}", e));
+            return false;
+        }
+    };
+
+    let result = authenticate_via_pam(user, service, &password);
+    zero_string(&mut password);
+    result
+}
+
+fn authenticate_via_pam(user: &str, service: &str, password: &str) -> bool {
+    let cuser = match CString::new(user) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+    let cservice = match CString::new(service) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+    let cpass = match CString::new(password) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+
+    // Boxed so the conversation callback's `appdata_ptr` stays valid for the
+    // lifetime of the PAM transaction; reclaimed and zeroed below.
+    let appdata_ptr = Box::into_raw(Box::new(cpass)) as *mut c_void;
+
+    let conv = pam_sys::PamConv { conv: password_conv, appdata_ptr };
+    let mut pamh: *mut pam_sys::PamHandle = ptr::null_mut();
+
+    let ok = unsafe {
+        let start_rc = pam_sys::pam_start(cservice.as_ptr(), cuser.as_ptr(), &conv, &mut pamh);
+        if start_rc != pam_sys::PAM_SUCCESS {
+            log_error(&format!("pam_start failed for service '{}' (code {})", service, start_rc));
+            false
+        } else {
+            let auth_rc = pam_sys::pam_authenticate(pamh, 0);
+            let acct_rc = if auth_rc == pam_sys::PAM_SUCCESS {
+                pam_sys::pam_acct_mgmt(pamh, 0)
+            } else {
+                auth_rc
+            };
+
+            match acct_rc {
+                pam_sys::PAM_SUCCESS => true,
+                pam_sys::PAM_ACCT_EXPIRED => {
+                    log_error(&format!("account '{}' has expired", user));
+                    false
+                }
+                pam_sys::PAM_AUTH_ERR => false,
+                code => {
+                    log_error(&format!("PAM authentication failed for '{}' (code {})", user, code));
+                    false
+                }
+            }
+        }
+    };
+
+    unsafe {
+        if !pamh.is_null() {
+            pam_sys::pam_end(pamh, if ok { pam_sys::PAM_SUCCESS } else { pam_sys::PAM_AUTH_ERR });
+        }
+        let mut boxed = Box::from_raw(appdata_ptr as *mut CString);
+        zero_cstring(&mut boxed);
+    }
+
+    ok
+}
+
+fn zero_string(s: &mut String) {
+    unsafe {
+        ptr::write_bytes(s.as_mut_vec().as_mut_ptr(), 0, s.len());
+    }
+    s.clear();
+}
+
+fn zero_cstring(s: &mut CString) {
+    // `CString` has no public mutable byte access, so scrub through the raw
+    // pointer it wraps; the one intentional reach past the safe API here.
+    unsafe {
+        let bytes = s.as_ptr() as *mut u8;
+        ptr::write_bytes(bytes, 0, s.as_bytes().len());
+    }
+}
+
+/// Minimal PAM FFI surface needed for an authenticate + account-management
+/// transaction. Linux-PAM and OpenPAM share the `pam_start`/`pam_authenticate`/
+/// `pam_acct_mgmt`/`pam_end` entry points and the `pam_conv` struct layout,
+/// but diverge in two ways that matter for a conversation callback: how the
+/// `msg` array is laid out in memory, and several `PAM_*` result codes. Both
+/// variants are implemented below and selected by `target_os`; `message_at`
+/// is each variant's answer to "how do I get message `i` out of `msg`".
+#[cfg(target_os = "linux")]
+mod pam_sys {
+    use super::*;
+
+    pub const PAM_SUCCESS: c_int = 0;
+    pub const PAM_AUTH_ERR: c_int = 7;
+    pub const PAM_ACCT_EXPIRED: c_int = 13;
+    pub const PAM_PROMPT_ECHO_OFF: c_int = 1;
+
+    #[repr(C)]
+    pub struct PamMessage {
+        pub msg_style: c_int,
+        pub msg: *const c_char,
+    }
+
+    #[repr(C)]
+    pub struct PamResponse {
+        pub resp: *mut c_char,
+        pub resp_retcode: c_int,
+    }
+
+    #[repr(C)]
+    pub struct PamConv {
+        pub conv: extern "C" fn(
+            num_msg: c_int,
+            msg: *mut *const PamMessage,
+            resp: *mut *mut PamResponse,
+            appdata_ptr: *mut c_void,
+        ) -> c_int,
+        pub appdata_ptr: *mut c_void,
+    }
+
+    #[repr(C)]
+    pub struct PamHandle {
+        _private: [u8; 0],
+    }
+
+    extern "C" {
+        pub fn pam_start(
+            service_name: *const c_char,
+            user: *const c_char,
+            pam_conversation: *const PamConv,
+            pamh: *mut *mut PamHandle,
+        ) -> c_int;
+
+        pub fn pam_authenticate(pamh: *mut PamHandle, flags: c_int) -> c_int;
+        pub fn pam_acct_mgmt(pamh: *mut PamHandle, flags: c_int) -> c_int;
+        pub fn pam_end(pamh: *mut PamHandle, pam_status: c_int) -> c_int;
+    }
+
+    /// Linux-PAM passes `msg` as an array of `num_msg` pointers-to-message:
+    /// element `i` is `msg[i]`.
+    pub unsafe fn message_at(msg: *mut *const PamMessage, i: usize) -> *const PamMessage {
+        *msg.add(i)
+    }
+}
+
+/// OpenPAM (FreeBSD/NetBSD/macOS) variant: same `pam_conv` signature and
+/// entry points as Linux-PAM, but `msg` points at one contiguous array of
+/// `num_msg` messages rather than an array of pointers, and its `PAM_*`
+/// result codes are numbered differently.
+#[cfg(not(target_os = "linux"))]
+mod pam_sys {
+    use super::*;
+
+    pub const PAM_SUCCESS: c_int = 0;
+    pub const PAM_AUTH_ERR: c_int = 9;
+    pub const PAM_ACCT_EXPIRED: c_int = 17;
+    pub const PAM_PROMPT_ECHO_OFF: c_int = 1;
+
+    #[repr(C)]
+    pub struct PamMessage {
+        pub msg_style: c_int,
+        pub msg: *const c_char,
+    }
+
+    #[repr(C)]
+    pub struct PamResponse {
+        pub resp: *mut c_char,
+        pub resp_retcode: c_int,
+    }
+
+    #[repr(C)]
+    pub struct PamConv {
+        pub conv: extern "C" fn(
+            num_msg: c_int,
+            msg: *mut *const PamMessage,
+            resp: *mut *mut PamResponse,
+            appdata_ptr: *mut c_void,
+        ) -> c_int,
+        pub appdata_ptr: *mut c_void,
+    }
+
+    #[repr(C)]
+    pub struct PamHandle {
+        _private: [u8; 0],
+    }
+
+    extern "C" {
+        pub fn pam_start(
+            service_name: *const c_char,
+            user: *const c_char,
+            pam_conversation: *const PamConv,
+            pamh: *mut *mut PamHandle,
+        ) -> c_int;
+
+        pub fn pam_authenticate(pamh: *mut PamHandle, flags: c_int) -> c_int;
+        pub fn pam_acct_mgmt(pamh: *mut PamHandle, flags: c_int) -> c_int;
+        pub fn pam_end(pamh: *mut PamHandle, pam_status: c_int) -> c_int;
+    }
+
+    /// OpenPAM passes `msg` as a pointer to a single contiguous array of
+    /// `num_msg` messages: element `i` is `(*msg).add(i)`, not `msg[i]`.
+    pub unsafe fn message_at(msg: *mut *const PamMessage, i: usize) -> *const PamMessage {
+        (*msg).add(i)
+    }
+}
+
+/// PAM conversation callback: answers every `PAM_PROMPT_ECHO_OFF` message
+/// (the password prompt) with the password stashed in `appdata_ptr` and
+/// leaves other message styles unanswered.
+extern "C" fn password_conv(
+    num_msg: c_int,
+    msg: *mut *const pam_sys::PamMessage,
+    resp: *mut *mut pam_sys::PamResponse,
+    appdata_ptr: *mut c_void,
+) -> c_int {
+    if num_msg <= 0 || msg.is_null() || appdata_ptr.is_null() {
+        return pam_sys::PAM_AUTH_ERR;
+    }
+
+    unsafe {
+        let password = &*(appdata_ptr as *const CString);
+        let count = num_msg as usize;
+        let responses = libc::calloc(count, std::mem::size_of::<pam_sys::PamResponse>())
+            as *mut pam_sys::PamResponse;
+        if responses.is_null() {
+            return pam_sys::PAM_AUTH_ERR;
+        }
+
+        for i in 0..count {
+            let m = pam_sys::message_at(msg, i);
+            let entry = responses.add(i);
+            (*entry).resp_retcode = 0;
+            (*entry).resp = if (*m).msg_style == pam_sys::PAM_PROMPT_ECHO_OFF {
+                libc::strdup(password.as_ptr())
+            } else {
+                ptr::null_mut()
+            };
+        }
+
+        *resp = responses;
+    }
+
+    pam_sys::PAM_SUCCESS
+}
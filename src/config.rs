@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Which mechanism `elev` uses to verify a user's password.
+#[derive(Debug, Clone, Default)]
+pub enum AuthBackend {
+    /// Defer to the system PAM stack under the given service name.
+    Pam { service: String },
+    /// elev's built-in password check against the local password database.
+    #[default]
+    Internal,
+}
+
+/// A user or group named on the left-hand side of a `permit` rule.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subject {
+    User(String),
+    Group(String),
+}
+
+/// How a rule matches the argv of the invoked command.
+#[derive(Debug, Clone)]
+pub enum ArgMatch {
+    /// The argv must match this list exactly.
+    Exact(Vec<String>),
+    /// The command must be invoked with no arguments at all.
+    NoArgs,
+    /// Any argv is permitted (`args any` in the config).
+    Arbitrary,
+}
+
+/// A single `permit` line: grants `subjects` permission to run `command`
+/// (with `args`) as `target_user`. Rules are evaluated in file order and the
+/// first one that matches a request wins, the same as doas.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub subjects: Vec<Subject>,
+    pub target_user: String,
+    /// Canonicalized at load time so it compares equal to the canonicalized
+    /// path `resolve_command_path` produces for the requested command, even
+    /// across usr-merge symlinks (e.g. `/bin/ls` -> `/usr/bin/ls`).
+    pub command: Option<PathBuf>,
+    pub args: ArgMatch,
+    pub nopass: bool,
+    /// Overrides the global `no_new_privs` setting for this rule, if set.
+    pub no_new_privs: Option<bool>,
+}
+
+impl Rule {
+    /// Returns true if this rule grants `user`/`user_groups` permission to
+    /// run `command` with `argv` as `target_user`.
+    pub fn applies(
+        &self,
+        user: &str,
+        user_groups: &[String],
+        target_user: &str,
+        command: &Path,
+        argv: &[String],
+    ) -> bool {
+        if self.target_user != target_user {
+            return false;
+        }
+
+        let subject_matches = self.subjects.iter().any(|s| match s {
+            Subject::User(name) => name == user,
+            Subject::Group(name) => user_groups.iter().any(|g| g == name),
+        });
+        if !subject_matches {
+            return false;
+        }
+
+        match &self.command {
+            None => true,
+            Some(allowed) => {
+                if allowed != command {
+                    return false;
+                }
+                match &self.args {
+                    ArgMatch::Arbitrary => true,
+                    ArgMatch::NoArgs => argv.is_empty(),
+                    ArgMatch::Exact(expected) => expected.as_slice() == argv,
+                }
+            }
+        }
+    }
+}
+
+/// Parsed contents of `/etc/elev.conf`.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// How long, in seconds, a successful authentication is cached for.
+    pub timeout: u64,
+    /// Authentication backend selected for password verification.
+    pub auth_backend: AuthBackend,
+    /// Ordered `permit` rules; first match wins.
+    pub rules: Vec<Rule>,
+    /// Environment variables passed through from the caller's environment,
+    /// in addition to the safe minimal set elev always reconstructs.
+    pub keep_env: Vec<String>,
+    /// Environment variables forced to fixed values regardless of the
+    /// caller's environment.
+    pub set_env: HashMap<String, String>,
+    /// Whether to set `PR_SET_NO_NEW_PRIVS` before running the target
+    /// command, by default. Rules may override this individually.
+    pub no_new_privs: bool,
+    /// Whether to run the target command attached to an allocated PTY by
+    /// default; overridable per-invocation with `--pty`.
+    pub pty: bool,
+    /// Whether audit records are additionally sent to syslog.
+    pub syslog_enabled: bool,
+    /// Syslog facility to tag audit records with (`LOG_AUTHPRIV` and
+    /// friends); defaults to `LOG_AUTHPRIV`.
+    pub syslog_facility: i32,
+    /// Whether `--chdir` is honored alongside `--login`. Off by default:
+    /// login shells start in `$HOME` unless an admin explicitly opts in.
+    pub allow_login_chdir: bool,
+}
+
+impl Config {
+    /// Load and parse the config file at `path`, falling back to sane
+    /// defaults for anything left unspecified.
+    pub fn load(path: &str) -> io::Result<Config> {
+        let mut timeout: u64 = 300;
+        let mut auth_backend = AuthBackend::Internal;
+        let mut rules = Vec::new();
+        let mut keep_env = Vec::new();
+        let mut set_env = HashMap::new();
+        let mut no_new_privs = false;
+        let mut pty = false;
+        let mut syslog_enabled = true;
+        let mut syslog_facility = libc::LOG_AUTHPRIV;
+        let mut allow_login_chdir = false;
+
+        if Path::new(path).exists() {
+            let contents = fs::read_to_string(path)?;
+            for raw_line in contents.lines() {
+                let line = raw_line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                if line.starts_with("permit") {
+                    if let Some(rule) = parse_rule_line(line) {
+                        rules.push(rule);
+                    }
+                    continue;
+                }
+
+                let mut parts = line.splitn(2, '=');
+                let key = parts.next().unwrap_or("").trim();
+                let value = parts.next().unwrap_or("").trim();
+
+                match key {
+                    "timeout" => {
+                        if let Ok(v) = value.parse() {
+                            timeout = v;
+                        }
+                    }
+                    "auth" => {
+                        auth_backend = if value == "internal" {
+                            AuthBackend::Internal
+                        } else if let Some(service) = value.strip_prefix("pam:") {
+                            AuthBackend::Pam { service: service.to_string() }
+                        } else if value == "pam" {
+                            AuthBackend::Pam { service: "elev".to_string() }
+                        } else {
+                            AuthBackend::Internal
+                        };
+                    }
+                    "keepenv" => {
+                        keep_env = value
+                            .split(',')
+                            .map(|s| s.trim().to_string())
+                            .filter(|s| !s.is_empty())
+                            .collect();
+                    }
+                    "setenv" => {
+                        for pair in value.split(',') {
+                            if let Some((k, v)) = pair.trim().split_once('=') {
+                                set_env.insert(k.trim().to_string(), v.trim().to_string());
+                            }
+                        }
+                    }
+                    "no_new_privs" => {
+                        no_new_privs = value == "true" || value == "1";
+                    }
+                    "pty" => {
+                        pty = value == "true" || value == "1";
+                    }
+                    "syslog" => {
+                        syslog_enabled = value == "true" || value == "1";
+                    }
+                    "syslog_facility" => {
+                        syslog_facility = parse_facility(value);
+                    }
+                    "allow_login_chdir" => {
+                        allow_login_chdir = value == "true" || value == "1";
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Config {
+            timeout,
+            auth_backend,
+            rules,
+            keep_env,
+            set_env,
+            no_new_privs,
+            pty,
+            syslog_enabled,
+            syslog_facility,
+            allow_login_chdir,
+        })
+    }
+
+    /// Find the first rule, in file order, that grants `user`/`user_groups`
+    /// permission to run `command` with `argv` as `target_user`.
+    pub fn authorize(
+        &self,
+        user: &str,
+        user_groups: &[String],
+        target_user: &str,
+        command: &Path,
+        argv: &[String],
+    ) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.applies(user, user_groups, target_user, command, argv))
+    }
+}
+
+/// Parse a single `permit` line:
+///
+///   permit [nopass] [nnp] <subject> [as <target>] [cmd <path> [args any|<arg>...]]
+///
+/// where `<subject>` is a username or `%group` and `nnp` opts the rule into
+/// `no_new_privs`, overriding the global default. Malformed lines are
+/// skipped, consistent with the permissive handling of the rest of the
+/// config.
+fn parse_rule_line(line: &str) -> Option<Rule> {
+    let mut tokens = line.split_whitespace().peekable();
+    tokens.next(); // "permit"
+
+    let mut nopass = false;
+    let mut no_new_privs = None;
+    while let Some(&tok) = tokens.peek() {
+        match tok {
+            "nopass" => {
+                nopass = true;
+                tokens.next();
+            }
+            "nnp" => {
+                no_new_privs = Some(true);
+                tokens.next();
+            }
+            _ => break,
+        }
+    }
+
+    let subject_tok = tokens.next()?;
+    let subjects = vec![parse_subject(subject_tok)];
+
+    let mut target_user = "root".to_string();
+    let mut command = None;
+    let mut args = ArgMatch::NoArgs;
+
+    while let Some(tok) = tokens.next() {
+        match tok {
+            "as" => {
+                target_user = tokens.next()?.to_string();
+            }
+            "cmd" => {
+                command = Some(canonicalize_or_self(PathBuf::from(tokens.next()?)));
+            }
+            "args" => {
+                let rest: Vec<String> = tokens.by_ref().map(|s| s.to_string()).collect();
+                args = if rest.len() == 1 && rest[0] == "any" {
+                    ArgMatch::Arbitrary
+                } else {
+                    ArgMatch::Exact(rest)
+                };
+            }
+            _ => return None,
+        }
+    }
+
+    Some(Rule { subjects, target_user, command, args, nopass, no_new_privs })
+}
+
+/// Map a `syslog_facility` config value to its `libc` facility constant,
+/// falling back to `LOG_AUTHPRIV` for anything unrecognized.
+fn parse_facility(value: &str) -> i32 {
+    match value {
+        "auth" => libc::LOG_AUTH,
+        "authpriv" => libc::LOG_AUTHPRIV,
+        "daemon" => libc::LOG_DAEMON,
+        "user" => libc::LOG_USER,
+        "local0" => libc::LOG_LOCAL0,
+        "local1" => libc::LOG_LOCAL1,
+        _ => libc::LOG_AUTHPRIV,
+    }
+}
+
+/// Canonicalize `path` (resolving symlinks like the usr-merge `/bin` ->
+/// `/usr/bin` so rule matching compares against the same form
+/// `resolve_command_path` produces), falling back to `path` unchanged if it
+/// doesn't exist at config-load time.
+fn canonicalize_or_self(path: PathBuf) -> PathBuf {
+    path.canonicalize().unwrap_or(path)
+}
+
+fn parse_subject(tok: &str) -> Subject {
+    match tok.strip_prefix('%') {
+        Some(group) => Subject::Group(group.to_string()),
+        None => Subject::User(tok.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(line: &str) -> Rule {
+        parse_rule_line(line).unwrap_or_else(|| panic!("failed to parse rule line: {}", line))
+    }
+
+    #[test]
+    fn parse_subject_distinguishes_user_and_group() {
+        assert_eq!(parse_subject("alice"), Subject::User("alice".to_string()));
+        assert_eq!(parse_subject("%wheel"), Subject::Group("wheel".to_string()));
+    }
+
+    #[test]
+    fn parse_rule_line_defaults() {
+        let r = rule("permit alice");
+        assert_eq!(r.subjects, vec![Subject::User("alice".to_string())]);
+        assert_eq!(r.target_user, "root");
+        assert!(r.command.is_none());
+        assert!(matches!(r.args, ArgMatch::NoArgs));
+        assert!(!r.nopass);
+        assert_eq!(r.no_new_privs, None);
+    }
+
+    #[test]
+    fn parse_rule_line_full() {
+        let r = rule("permit nopass nnp %wheel as deploy cmd /opt/testbin/ls args any");
+        assert_eq!(r.subjects, vec![Subject::Group("wheel".to_string())]);
+        assert_eq!(r.target_user, "deploy");
+        assert!(r.command.is_some());
+        assert!(matches!(r.args, ArgMatch::Arbitrary));
+        assert!(r.nopass);
+        assert_eq!(r.no_new_privs, Some(true));
+    }
+
+    #[test]
+    fn parse_rule_line_exact_args() {
+        let r = rule("permit alice cmd /opt/testbin/systemctl args restart nginx");
+        match r.args {
+            ArgMatch::Exact(args) => assert_eq!(args, vec!["restart".to_string(), "nginx".to_string()]),
+            other => panic!("expected ArgMatch::Exact, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_rule_line_rejects_malformed() {
+        assert!(parse_rule_line("permit alice bogus-token").is_none());
+    }
+
+    #[test]
+    fn applies_matches_user_subject() {
+        let r = rule("permit alice");
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+        assert!(!r.applies("bob", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+    }
+
+    #[test]
+    fn applies_matches_group_subject() {
+        let r = rule("permit %wheel");
+        let groups = vec!["users".to_string(), "wheel".to_string()];
+        assert!(r.applies("alice", &groups, "root", Path::new("/opt/testbin/ls"), &[]));
+        assert!(!r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+    }
+
+    #[test]
+    fn applies_checks_target_user() {
+        let r = rule("permit alice as deploy");
+        assert!(r.applies("alice", &[], "deploy", Path::new("/opt/testbin/ls"), &[]));
+        assert!(!r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+    }
+
+    #[test]
+    fn applies_with_no_command_matches_any_command() {
+        let r = rule("permit alice");
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/vim"), &["file.txt".to_string()]));
+    }
+
+    #[test]
+    fn applies_with_command_requires_path_match() {
+        let r = rule("permit alice cmd /opt/testbin/ls");
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+        assert!(!r.applies("alice", &[], "root", Path::new("/opt/testbin/cat"), &[]));
+    }
+
+    #[test]
+    fn applies_no_args_rule_requires_empty_argv() {
+        let r = rule("permit alice cmd /opt/testbin/ls");
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+        assert!(!r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &["-la".to_string()]));
+    }
+
+    #[test]
+    fn applies_arbitrary_args_matches_anything() {
+        let r = rule("permit alice cmd /opt/testbin/ls args any");
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &[]));
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/ls"), &["-la".to_string(), "/tmp".to_string()]));
+    }
+
+    #[test]
+    fn applies_exact_args_must_match_exactly() {
+        let r = rule("permit alice cmd /opt/testbin/systemctl args restart nginx");
+        let argv = vec!["restart".to_string(), "nginx".to_string()];
+        assert!(r.applies("alice", &[], "root", Path::new("/opt/testbin/systemctl"), &argv));
+        assert!(!r.applies("alice", &[], "root", Path::new("/opt/testbin/systemctl"), &["restart".to_string()]));
+    }
+
+    #[test]
+    fn authorize_returns_first_matching_rule_in_file_order() {
+        let config = Config {
+            timeout: 300,
+            auth_backend: AuthBackend::Internal,
+            rules: vec![
+                rule("permit alice as root cmd /opt/testbin/ls"),
+                rule("permit alice"),
+            ],
+            keep_env: Vec::new(),
+            set_env: HashMap::new(),
+            no_new_privs: false,
+            pty: false,
+            syslog_enabled: false,
+            syslog_facility: libc::LOG_AUTHPRIV,
+            allow_login_chdir: false,
+        };
+
+        let matched = config
+            .authorize("alice", &[], "root", Path::new("/opt/testbin/ls"), &[])
+            .expect("expected a matching rule");
+        assert!(matched.command.is_some());
+
+        let matched = config
+            .authorize("alice", &[], "root", Path::new("/opt/testbin/cat"), &[])
+            .expect("expected the catch-all rule to match");
+        assert!(matched.command.is_none());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::Config;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static SYSLOG_ENABLED: AtomicBool = AtomicBool::new(false);
+static SYSLOG_FACILITY: AtomicI32 = AtomicI32::new(libc::LOG_AUTHPRIV);
+
+/// Largest chunk of text handed to a single `syslog()` call. Oversized
+/// messages (e.g. a command line built from attacker-controlled argv) are
+/// split across multiple bounded calls rather than passed through whole, so
+/// a huge argv can't be used to crash or truncate-corrupt the log sink.
+const SYSLOG_CHUNK_SIZE: usize = 900;
+
+/// Initialize elev's logger. When `verbose` is set, info-level messages are
+/// printed in addition to warnings and errors.
+pub fn init_logger(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Initialize the syslog sink from `config`. Audit records are then mirrored
+/// to syslog (facility `config.syslog_facility`, default `LOG_AUTHPRIV`) in
+/// addition to elev's own logger.
+pub fn init_syslog(config: &Config) {
+    if !config.syslog_enabled {
+        return;
+    }
+
+    SYSLOG_FACILITY.store(config.syslog_facility, Ordering::Relaxed);
+    SYSLOG_ENABLED.store(true, Ordering::Relaxed);
+
+    // `openlog`'s ident pointer must stay valid for the process lifetime;
+    // elev runs once per invocation and exits, so leaking it is fine.
+    let ident: &'static CStr = {
+        let boxed = Box::new(CString::new("elev").unwrap());
+        Box::leak(boxed)
+    };
+    unsafe {
+        libc::openlog(ident.as_ptr(), libc::LOG_PID | libc::LOG_NDELAY, config.syslog_facility);
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Log an informational message. Only emitted when verbose logging is enabled.
+pub fn log_info(msg: &str) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("[{}] INFO: {}", timestamp(), msg);
+    }
+}
+
+/// Log a warning message.
+pub fn log_warn(msg: &str) {
+    eprintln!("[{}] WARN: {}", timestamp(), msg);
+}
+
+/// Log an error message.
+pub fn log_error(msg: &str) {
+    eprintln!("[{}] ERROR: {}", timestamp(), msg);
+}
+
+/// A single access-control decision, suitable for tamper-resistant audit
+/// logging: who invoked elev, as whom, running what, and what happened.
+pub struct AuditRecord<'a> {
+    pub user: &'a str,
+    pub target_user: &'a str,
+    pub command: &'a str,
+    pub argv: &'a [String],
+    pub outcome: &'a str,
+    pub auth_result: &'a str,
+    pub tty: &'a str,
+}
+
+/// Record an audit decision through elev's own logger and, if configured,
+/// syslog's `LOG_AUTHPRIV` (or configured facility).
+pub fn log_audit(record: &AuditRecord) {
+    let line = format!(
+        "user={} target={} command={} argv=[{}] outcome={} auth={} tty={}",
+        record.user,
+        record.target_user,
+        record.command,
+        record.argv.join(" "),
+        record.outcome,
+        record.auth_result,
+        record.tty,
+    );
+
+    log_info(&line);
+    syslog_write(libc::LOG_INFO, &line);
+}
+
+/// The terminal attached to standard input, or `"none"` if stdin is not a
+/// terminal (e.g. invoked from a non-interactive context or with stdin
+/// redirected).
+pub fn current_tty() -> String {
+    unsafe {
+        let ptr = libc::ttyname(0);
+        if ptr.is_null() {
+            "none".to_string()
+        } else {
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        }
+    }
+}
+
+fn syslog_write(priority: c_int, message: &str) {
+    if !SYSLOG_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let facility = SYSLOG_FACILITY.load(Ordering::Relaxed);
+    let sanitized = message.replace('\0', "");
+    let bytes = sanitized.as_bytes();
+
+    if bytes.len() <= SYSLOG_CHUNK_SIZE {
+        syslog_line(facility | priority, &sanitized);
+        return;
+    }
+
+    let total = bytes.len().div_ceil(SYSLOG_CHUNK_SIZE);
+    for (i, chunk) in bytes.chunks(SYSLOG_CHUNK_SIZE).enumerate() {
+        let part = String::from_utf8_lossy(chunk);
+        syslog_line(facility | priority, &format!("[{}/{}] {}", i + 1, total, part));
+    }
+}
+
+fn syslog_line(priority: c_int, line: &str) {
+    if let Ok(cline) = CString::new(line) {
+        unsafe {
+            libc::syslog(priority, c"%s".as_ptr(), cline.as_ptr());
+        }
+    }
+}
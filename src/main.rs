@@ -2,6 +2,7 @@ mod config;
 mod auth;
 mod util;
 mod logs;
+mod pty;
 
 use clap::{Arg, Command};
 use config::Config;
@@ -9,7 +10,7 @@ use std::os::unix::process::CommandExt;
 use std::process::{exit, Command as ProcessCommand};
 use util::{get_user_groups, switch_user, run_command};
 use auth::{verify_password, AuthState};
-use logs::{init_logger, log_info, log_warn, log_error};
+use logs::{init_logger, init_syslog, log_info, log_warn, log_error, log_audit, current_tty, AuditRecord};
 use nix::unistd::{getuid, geteuid, User};
 use nix::libc;
 use std::ffi::CStr;
@@ -90,6 +91,28 @@ fn main() {
                 .help("Enable verbose logging")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("pty")
+                .long("pty")
+                .help("Run the command attached to an allocated pseudo-terminal")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("group")
+                .short('g')
+                .long("group")
+                .help("Target group to run command as, overriding the target user's primary group")
+                .value_name("GROUP")
+                .value_parser(clap::value_parser!(String)),
+        )
+        .arg(
+            Arg::new("chdir")
+                .short('D')
+                .long("chdir")
+                .help("Working directory for the executed command")
+                .value_name("DIR")
+                .value_parser(clap::value_parser!(String)),
+        )
         .get_matches();
 
     // Initialize logging
@@ -102,9 +125,12 @@ fn main() {
 
     // Who to run command as
     let target_user = matches.get_one::<String>("user").map(String::as_str).unwrap_or("root");
+    let target_group = matches.get_one::<String>("group").map(String::as_str);
+    let chdir_arg = matches.get_one::<String>("chdir").map(String::as_str);
 
     let config = Config::load("/etc/elev.conf").expect("Failed to load config");
-    let mut auth_state = AuthState::new(config.timeout, current_user.clone(), groups.clone(), &config);
+    init_syslog(&config);
+    let mut auth_state = AuthState::new(config.timeout, current_user.clone(), groups.clone());
 
     // Reset authentication timestamp (-K)
     if matches.get_flag("reset_auth") {
@@ -116,13 +142,9 @@ fn main() {
 
     // Login shell mode (-i)
     if matches.get_flag("login") {
-        // Switch to target user
-        if let Err(e) = switch_user(target_user) {
-            log_error(&format!("Failed to switch to user '{}': {}", target_user, e));
-            exit(1);
-        }
-
-        // Lookup target user's info
+        // Lookup target user's info first: authorization is keyed off the
+        // shell that will actually run, the same as run_command keys off
+        // the resolved command path.
         let user_entry = match User::from_name(target_user) {
             Ok(Some(u)) => u,
             Ok(None) => {
@@ -137,15 +159,96 @@ fn main() {
         let home_dir = user_entry.dir;
         let shell_path = user_entry.shell;
 
+        // A login shell carries no argv of its own, so it matches a rule the
+        // same way a no-args command invocation would. Canonicalize the
+        // shell path first, the same as `resolve_command_path` does for an
+        // ordinary command, so it compares equal to a canonicalized rule
+        // `cmd` on a usr-merged system.
+        let tty = current_tty();
+        let shell_path_resolved = shell_path.canonicalize().unwrap_or_else(|_| shell_path.clone());
+        let (nopass, rule_no_new_privs) = match config.authorize(&current_user, &groups, target_user, &shell_path_resolved, &[]) {
+            Some(r) => (r.nopass, r.no_new_privs),
+            None => {
+                let msg = format!(
+                    "'{}' is not permitted to run a login shell as '{}'",
+                    current_user, target_user
+                );
+                log_warn(&format!("denied: {}", msg));
+                log_audit(&AuditRecord {
+                    user: &current_user,
+                    target_user,
+                    command: "-i",
+                    argv: &[],
+                    outcome: "denied",
+                    auth_result: "n/a",
+                    tty: &tty,
+                });
+                log_error(&msg);
+                exit(1);
+            }
+        };
+
+        // Enforce timeout and password, same as the ordinary command path;
+        // a login shell is still a root shell and must be authenticated
+        // unless a `nopass` rule explicitly permits it.
+        if !nopass && !auth_state.check_timeout() {
+            log_warn("Authentication timeout expired, re-enter password.");
+            if !verify_password(&current_user, &mut auth_state, &config, target_user, "-i") {
+                log_error("Authentication failed");
+                exit(1);
+            }
+        }
+
+        log_info(&format!(
+            "rule granted: '{}' -> login shell as '{}'",
+            current_user, target_user
+        ));
+        log_audit(&AuditRecord {
+            user: &current_user,
+            target_user,
+            command: "-i",
+            argv: &[],
+            outcome: "granted",
+            auth_result: if auth_state.check_timeout() { "authenticated" } else { "nopass" },
+            tty: &tty,
+        });
+
+        // Switch to target user
+        if let Err(e) = switch_user(target_user, target_group) {
+            log_error(&format!("Failed to switch to user '{}': {}", target_user, e));
+            exit(1);
+        }
+
         // Launch login shell
         let mut shell = ProcessCommand::new(&shell_path);
         shell.arg("-l"); // login shell flag
-        shell.env("HOME", &home_dir);
-        shell.env("USER", target_user);
-        shell.env("LOGNAME", target_user);
-        shell.env("SHELL", &shell_path);
+        util::apply_sanitized_env(&mut shell, &config, target_user);
         shell.env("PS1", r"\u@\h: \w\$ ");
-        shell.current_dir(&home_dir);
+
+        // --login takes precedence over --chdir: the login shell starts in
+        // $HOME unless the admin has explicitly opted into honoring --chdir
+        // alongside --login.
+        let effective_dir = match chdir_arg {
+            Some(dir) if config.allow_login_chdir => std::path::PathBuf::from(dir),
+            _ => home_dir.clone(),
+        };
+        if !effective_dir.is_dir() {
+            log_error(&format!("chdir target '{}' does not exist or is not accessible", effective_dir.display()));
+            exit(1);
+        }
+        log_info(&format!(
+            "working directory: requested='{}' effective='{}'",
+            chdir_arg.unwrap_or(""),
+            effective_dir.display()
+        ));
+        shell.current_dir(&effective_dir);
+
+        if rule_no_new_privs.unwrap_or(config.no_new_privs) {
+            if let Err(e) = util::apply_no_new_privs() {
+                log_error(&format!("Failed to set no_new_privs: {}", e));
+                exit(1);
+            }
+        }
 
         // Replace current process
         let err = shell.exec();
@@ -171,19 +274,31 @@ fn main() {
         exit(1);
     });
 
-    let mut auth_state = AuthState::new(config.timeout, current_user.clone(), groups.clone(), &config);
+    let mut auth_state = AuthState::new(config.timeout, current_user.clone(), groups.clone());
+
+    // A rule may grant this exact (subject, target, command, argv) tuple
+    // without requiring a password (doas-style `nopass`).
+    let nopass = util::resolve_command_path(command)
+        .ok()
+        .and_then(|resolved| {
+            let argv: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            config.authorize(&current_user, &groups, target_user, &resolved, &argv)
+        })
+        .map(|rule| rule.nopass)
+        .unwrap_or(false);
 
     // Enforce timeout and password
-    if !auth_state.check_timeout() {
+    if !nopass && !auth_state.check_timeout() {
         log_warn("Authentication timeout expired, re-enter password.");
-        if !verify_password(&current_user, &mut auth_state, &config, &target_user, &command) {
+        if !verify_password(&current_user, &mut auth_state, &config, target_user, command) {
             log_error("Authentication failed");
             exit(1);
         }
     }
 
     // Run the command
-    run_command(command, &args, target_user, &config, &mut auth_state).unwrap_or_else(|e| {
+    let use_pty = *matches.get_one::<bool>("pty").unwrap_or(&false) || config.pty;
+    run_command(command, &args, target_user, &config, &mut auth_state, use_pty, target_group, chdir_arg).unwrap_or_else(|e| {
         use std::io::ErrorKind;
 
         match e.kind() {
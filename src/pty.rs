@@ -0,0 +1,183 @@
+use nix::libc;
+use nix::pty::openpty;
+use nix::sys::signal::{self, SigHandler, Signal};
+use nix::sys::termios::{self, SetArg};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::{close, dup2, fork, read, setsid, write, ForkResult, Pid};
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+static WINCH_RECEIVED: AtomicBool = AtomicBool::new(false);
+static CHILD_PID: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn on_winch(_: libc::c_int) {
+    WINCH_RECEIVED.store(true, Ordering::SeqCst);
+}
+
+/// Forwards SIGINT/SIGTERM/SIGTSTP to the child's process group so job
+/// control and Ctrl-C behave the way they would without elev in between.
+extern "C" fn on_forward(sig: libc::c_int) {
+    let pid = CHILD_PID.load(Ordering::SeqCst);
+    if pid > 0 {
+        unsafe {
+            libc::kill(-pid, sig);
+        }
+    }
+}
+
+/// Run `cmd` attached to a freshly allocated pseudo-terminal: the child
+/// becomes its own session leader with the PTY slave as controlling
+/// terminal, and the parent relays bytes, window-size changes, and signals
+/// between the real terminal and the PTY master until the child exits.
+/// Returns the child's exit status.
+pub fn run_in_pty(mut cmd: Command) -> io::Result<i32> {
+    let pty = openpty(None, None).map_err(to_io_err)?;
+    let master = pty.master;
+    let slave = pty.slave;
+
+    match unsafe { fork() }.map_err(to_io_err)? {
+        ForkResult::Child => {
+            let _ = close(master);
+            unsafe {
+                if setsid().is_err() || libc::ioctl(slave, libc::TIOCSCTTY as _, 0) != 0 {
+                    libc::_exit(1);
+                }
+            }
+            for fd in 0..=2 {
+                let _ = dup2(slave, fd);
+            }
+            if slave > 2 {
+                let _ = close(slave);
+            }
+
+            let err = cmd.exec();
+            eprintln!("elev: failed to exec in pty: {}", err);
+            unsafe { libc::_exit(1) };
+        }
+        ForkResult::Parent { child } => {
+            let _ = close(slave);
+            CHILD_PID.store(child.as_raw(), Ordering::SeqCst);
+            relay(master, child)
+        }
+    }
+}
+
+fn relay(master: RawFd, child: Pid) -> io::Result<i32> {
+    let stdin_fd = io::stdin().as_raw_fd();
+    let stdout_fd = io::stdout().as_raw_fd();
+
+    let original_termios = termios::tcgetattr(stdin_fd).ok();
+    if let Some(ref t) = original_termios {
+        let mut raw = t.clone();
+        termios::cfmakeraw(&mut raw);
+        let _ = termios::tcsetattr(stdin_fd, SetArg::TCSANOW, &raw);
+    }
+
+    sync_window_size(stdout_fd, master);
+
+    unsafe {
+        let _ = signal::signal(Signal::SIGWINCH, SigHandler::Handler(on_winch));
+        let _ = signal::signal(Signal::SIGINT, SigHandler::Handler(on_forward));
+        let _ = signal::signal(Signal::SIGTERM, SigHandler::Handler(on_forward));
+        let _ = signal::signal(Signal::SIGTSTP, SigHandler::Handler(on_forward));
+    }
+
+    let mut buf = [0u8; 4096];
+    let result = loop {
+        if WINCH_RECEIVED.swap(false, Ordering::SeqCst) {
+            sync_window_size(stdout_fd, master);
+        }
+
+        if let Some(status) = poll_child_exit(child) {
+            // The child may have written its last output just before
+            // exiting; drain whatever is still buffered in the master side
+            // so it isn't silently dropped.
+            drain_master(master, stdout_fd, &mut buf);
+            break Ok(status);
+        }
+
+        let mut fds = [
+            libc::pollfd { fd: stdin_fd, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: master, events: libc::POLLIN, revents: 0 },
+        ];
+        let rc = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, 200) };
+        if rc < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            break Err(err);
+        }
+
+        if fds[0].revents & libc::POLLIN != 0 {
+            if let Ok(n) = read(stdin_fd, &mut buf) {
+                if n > 0 {
+                    let _ = write(master, &buf[..n]);
+                }
+            }
+        }
+
+        if fds[1].revents & libc::POLLIN != 0 {
+            match read(master, &mut buf) {
+                Ok(0) | Err(_) => {
+                    // The slave side has closed; wait for the child so its
+                    // own exit status is what gets returned.
+                    break match waitpid(child, None) {
+                        Ok(WaitStatus::Exited(_, code)) => Ok(code),
+                        Ok(WaitStatus::Signaled(_, sig, _)) => Ok(128 + sig as i32),
+                        Ok(other) => Err(io::Error::other(format!("unexpected wait status: {:?}", other))),
+                        Err(e) => Err(to_io_err(e)),
+                    };
+                }
+                Ok(n) => {
+                    let _ = write(stdout_fd, &buf[..n]);
+                }
+            }
+        }
+    };
+
+    if let Some(t) = original_termios {
+        let _ = termios::tcsetattr(stdin_fd, SetArg::TCSANOW, &t);
+    }
+    let _ = close(master);
+
+    result
+}
+
+/// Read and flush to `stdout_fd` whatever is left on `master` until it hits
+/// EOF or an error, for the post-exit drain where there's no more polling to
+/// wait for further output.
+fn drain_master(master: RawFd, stdout_fd: RawFd, buf: &mut [u8]) {
+    loop {
+        match read(master, buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let _ = write(stdout_fd, &buf[..n]);
+            }
+        }
+    }
+}
+
+fn poll_child_exit(child: Pid) -> Option<i32> {
+    match waitpid(child, Some(WaitPidFlag::WNOHANG)) {
+        Ok(WaitStatus::Exited(_, code)) => Some(code),
+        Ok(WaitStatus::Signaled(_, sig, _)) => Some(128 + sig as i32),
+        _ => None,
+    }
+}
+
+fn sync_window_size(from_fd: RawFd, to_fd: RawFd) {
+    unsafe {
+        let mut ws: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(from_fd, libc::TIOCGWINSZ, &mut ws) == 0 {
+            libc::ioctl(to_fd, libc::TIOCSWINSZ, &ws);
+        }
+    }
+}
+
+fn to_io_err(e: nix::Error) -> io::Error {
+    io::Error::from_raw_os_error(e as i32)
+}
@@ -0,0 +1,244 @@
+use crate::auth::AuthState;
+use crate::config::Config;
+use crate::logs::{current_tty, log_audit, log_info, log_warn, AuditRecord};
+use nix::unistd::{initgroups, setgid, setuid, Group, User};
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::c_int;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Resolve the group names `username` belongs to, via the system group
+/// database (primary group plus all supplementary groups).
+pub fn get_user_groups(username: &str) -> Vec<String> {
+    let cuser = match CString::new(username) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    unsafe {
+        let pw = libc::getpwnam(cuser.as_ptr());
+        if pw.is_null() {
+            return Vec::new();
+        }
+        let primary_gid = (*pw).pw_gid;
+
+        let mut ngroups: c_int = 32;
+        let mut gids: Vec<libc::gid_t> = vec![0; ngroups as usize];
+        while libc::getgrouplist(cuser.as_ptr(), primary_gid, gids.as_mut_ptr(), &mut ngroups) < 0 {
+            gids.resize(ngroups as usize, 0);
+        }
+        gids.truncate(ngroups as usize);
+
+        gids.into_iter()
+            .filter_map(|gid| {
+                let grp = libc::getgrgid(gid);
+                if grp.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr((*grp).gr_name).to_string_lossy().into_owned())
+                }
+            })
+            .collect()
+    }
+}
+
+/// Drop privileges from root to `target`, establishing their full
+/// supplementary group membership in the canonical order: resolve the
+/// primary (or `-g`-overridden) GID, `initgroups` to load supplementary
+/// groups from the group database, then `setgid`, then `setuid`. Each step
+/// is checked; any failure aborts rather than leaving privileges dropped
+/// only partway.
+pub fn switch_user(target: &str, override_group: Option<&str>) -> io::Result<()> {
+    let user = User::from_name(target)
+        .map_err(|e| io::Error::other(e.to_string()))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("user '{}' not found", target)))?;
+
+    let primary_gid = match override_group {
+        Some(name) => {
+            Group::from_name(name)
+                .map_err(|e| io::Error::other(e.to_string()))?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("group '{}' not found", name)))?
+                .gid
+        }
+        None => user.gid,
+    };
+
+    let ctarget = CString::new(target)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+
+    initgroups(&ctarget, primary_gid).map_err(|e| io::Error::other(e.to_string()))?;
+    setgid(primary_gid).map_err(|e| io::Error::other(e.to_string()))?;
+    setuid(user.uid).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(())
+}
+
+/// Resolve `command` to a canonical path: if it contains a `/` it's used
+/// as-is, otherwise it's looked up on `PATH`. Canonicalizing here (rather
+/// than trusting the argv string) is what lets rule matching in `Config`
+/// compare against the binary that will actually run.
+pub fn resolve_command_path(command: &str) -> io::Result<PathBuf> {
+    let candidate = if command.contains('/') {
+        PathBuf::from(command)
+    } else {
+        find_in_path(command).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("command '{}' not found in PATH", command))
+        })?
+    };
+    candidate.canonicalize()
+}
+
+fn find_in_path(command: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(command))
+        .find(|p| p.is_file())
+}
+
+/// Caller-environment variables passed through as-is (plus `config.keep_env`),
+/// regardless of config. `USER`/`LOGNAME`/`HOME`/`SHELL` are deliberately not
+/// here: they're reconstructed from `target_user`'s passwd entry below rather
+/// than carried over from the invoker, mirroring sudo's `env_reset`.
+const SAFE_ENV_VARS: &[&str] = &["PATH", "TERM"];
+
+/// Build the sanitized environment for the target command: `USER`,
+/// `LOGNAME`, `HOME` and `SHELL` reconstructed from `target_user`'s passwd
+/// entry, the safe pass-through set, plus anything the caller's environment
+/// supplies that is on `config.keep_env`, plus `config.set_env` forced last
+/// so it always wins.
+pub fn sanitized_env(config: &Config, target_user: &str) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+
+    if let Ok(Some(user)) = User::from_name(target_user) {
+        env.push(("HOME".to_string(), user.dir.display().to_string()));
+        env.push(("USER".to_string(), target_user.to_string()));
+        env.push(("LOGNAME".to_string(), target_user.to_string()));
+        env.push(("SHELL".to_string(), user.shell.display().to_string()));
+    }
+
+    let names = SAFE_ENV_VARS.iter().copied().chain(config.keep_env.iter().map(String::as_str));
+    for key in names {
+        if let Ok(val) = std::env::var(key) {
+            env.push((key.to_string(), val));
+        }
+    }
+
+    for (key, val) in &config.set_env {
+        env.push((key.clone(), val.clone()));
+    }
+
+    env
+}
+
+/// Replace `cmd`'s environment with the sanitized one built from `config`
+/// for `target_user`.
+pub fn apply_sanitized_env(cmd: &mut Command, config: &Config, target_user: &str) {
+    cmd.env_clear();
+    for (key, val) in sanitized_env(config, target_user) {
+        cmd.env(key, val);
+    }
+}
+
+/// Set `PR_SET_NO_NEW_PRIVS` on the current process so the target command
+/// (and anything it execs) can never regain privileges through a setuid
+/// binary. Must run before `exec`.
+pub fn apply_no_new_privs() -> io::Result<()> {
+    let rc = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Authorize and execute `command` with `args` as `target_user`. Runs
+/// directly via `exec` (replacing the current process) unless `use_pty` is
+/// set, in which case the command is attached to an allocated PTY and this
+/// function exits the process itself once it finishes.
+#[allow(clippy::too_many_arguments)]
+pub fn run_command(
+    command: &str,
+    args: &[&str],
+    target_user: &str,
+    config: &Config,
+    auth_state: &mut AuthState,
+    use_pty: bool,
+    group: Option<&str>,
+    chdir: Option<&str>,
+) -> io::Result<()> {
+    let resolved = resolve_command_path(command)?;
+    let argv: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    let tty = current_tty();
+    let auth_result = if auth_state.check_timeout() { "authenticated" } else { "nopass" };
+
+    let rule = config.authorize(auth_state.user(), auth_state.groups(), target_user, &resolved, &argv);
+    let no_new_privs = match rule {
+        Some(rule) => {
+            log_info(&format!(
+                "rule granted: '{}' -> '{}' as '{}'",
+                auth_state.user(),
+                resolved.display(),
+                target_user
+            ));
+            log_audit(&AuditRecord {
+                user: auth_state.user(),
+                target_user,
+                command: &resolved.display().to_string(),
+                argv: &argv,
+                outcome: "granted",
+                auth_result,
+                tty: &tty,
+            });
+            rule.no_new_privs.unwrap_or(config.no_new_privs)
+        }
+        None => {
+            let msg = format!(
+                "'{}' is not permitted to run '{}' as '{}'",
+                auth_state.user(),
+                resolved.display(),
+                target_user
+            );
+            log_warn(&format!("denied: {}", msg));
+            log_audit(&AuditRecord {
+                user: auth_state.user(),
+                target_user,
+                command: &resolved.display().to_string(),
+                argv: &argv,
+                outcome: "denied",
+                auth_result,
+                tty: &tty,
+            });
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, msg));
+        }
+    };
+
+    switch_user(target_user, group)?;
+
+    if no_new_privs {
+        apply_no_new_privs()?;
+    }
+
+    let mut cmd = Command::new(&resolved);
+    cmd.args(args);
+    apply_sanitized_env(&mut cmd, config, target_user);
+
+    if let Some(dir) = chdir {
+        let path = Path::new(dir);
+        // Checked as the target user: switch_user() has already dropped
+        // privileges above, so this reflects what the command itself will see.
+        if !path.is_dir() {
+            let msg = format!("chdir target '{}' does not exist or is not accessible", dir);
+            log_warn(&msg);
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+        }
+        log_info(&format!("working directory: requested='{}' effective='{}'", dir, dir));
+        cmd.current_dir(path);
+    }
+
+    if use_pty {
+        let status = crate::pty::run_in_pty(cmd)?;
+        std::process::exit(status);
+    }
+
+    Err(cmd.exec())
+}